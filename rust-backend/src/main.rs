@@ -1,14 +1,192 @@
-use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder, middleware::Logger};
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler as ActixStreamHandler};
+use actix_web::{get, post, web, App, Error, HttpRequest, HttpResponse, HttpServer, Responder, middleware::Logger};
+use actix_web_actors::ws;
+use dashmap::DashMap;
 use log::error;
 use parking_lot::Mutex;
 use reqwest::Client;
 use log::info;
 use chrono::Utc;
 use serde_json::Value;
+use socket2::{Domain, Protocol, Socket, TcpKeepalive, Type};
+use std::collections::{HashMap, VecDeque};
 use std::fs;
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use uuid::Uuid;
+
+/// Identifies one caller's demo run. Minted by `/demo/start` and then threaded
+/// through `/upload`, `/data`, `/demo/stop`, and `/ws` so concurrent callers
+/// never share a buffer.
+type SessionId = Uuid;
+
+/// A session is dropped (and its buffer reclaimed) after this long without
+/// an `/upload`, `/data`, or `/ws` request touching it.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// How often the idle-session sweep runs.
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the socket checks the client is still alive
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// Drop the socket if no pong has been seen for this long
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Backlog size for the packet/result broadcast channel
+const BROADCAST_CAPACITY: usize = 256;
+
+/// Rows kept per session for pipelined streaming inference, independent of
+/// the full buffer `/demo/stop` eventually sends.
+const STREAM_WINDOW_SIZE: usize = 30;
+
+/// One batch of newly accepted CSV rows to run through streaming inference.
+struct StreamChunk {
+    session_id: SessionId,
+    rows: Vec<String>,
+}
+
+/// Cap on the exponential part of the backoff between inference retries,
+/// regardless of how many attempts have already failed; jitter of up to a
+/// fifth of the capped value is added on top.
+const MAX_INFERENCE_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Retry policy for calls to the inference microservice, tunable per
+/// deployment via environment variables so operators don't need a rebuild to
+/// adapt to a slower or flakier upstream.
+struct InferenceConfig {
+    max_attempts: u32,
+    timeout: Duration,
+    base_backoff: Duration,
+}
+
+impl InferenceConfig {
+    fn from_env() -> Self {
+        Self {
+            max_attempts: env_var_or("INFERENCE_MAX_ATTEMPTS", 3),
+            timeout: Duration::from_millis(env_var_or("INFERENCE_TIMEOUT_MS", 10_000)),
+            base_backoff: Duration::from_millis(env_var_or("INFERENCE_BACKOFF_BASE_MS", 200)),
+        }
+    }
+}
+
+fn env_var_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Cheap jitter source: nanosecond-resolution elapsed time since the first
+/// call is unpredictable enough to spread out retries without pulling in a
+/// `rand` dependency just for this.
+fn jitter_nanos(bound: u32) -> u32 {
+    static START: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+    if bound == 0 {
+        return 0;
+    }
+    let start = *START.get_or_init(Instant::now);
+    start.elapsed().subsec_nanos() % bound
+}
+
+fn backoff_for_attempt(config: &InferenceConfig, attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(10);
+    let scaled = config.base_backoff.saturating_mul(1u32 << exponent).min(MAX_INFERENCE_BACKOFF);
+    let jitter = Duration::from_nanos(jitter_nanos((scaled.as_nanos() / 5).min(u32::MAX as u128) as u32) as u64);
+    scaled + jitter
+}
+
+/// Result of (possibly repeated) attempts to reach the inference microservice.
+struct InferenceOutcome {
+    status: reqwest::StatusCode,
+    json: Option<Value>,
+    text: String,
+    attempts: u32,
+}
+
+impl InferenceOutcome {
+    fn into_response(self) -> HttpResponse {
+        let builder_status = if self.status.is_success() {
+            actix_web::http::StatusCode::OK
+        } else {
+            actix_web::http::StatusCode::BAD_GATEWAY
+        };
+        HttpResponse::build(builder_status).json(serde_json::json!({
+            "attempts": self.attempts,
+            "upstream_status": self.status.as_u16(),
+            "result": self.json.unwrap_or_else(|| Value::String(self.text)),
+        }))
+    }
+}
+
+/// Posts `csv` to the inference microservice, retrying with exponential
+/// backoff and jitter on connection errors, timeouts, and 5xx responses.
+/// 4xx responses are never retried — they indicate a request we'd just send
+/// again unchanged. Returns `Err` when no response was produced: at once for
+/// a request error that is neither a timeout nor a connection error, or once
+/// every attempt has failed that way (e.g. the service stayed unreachable).
+///
+/// Each HTTP attempt is timed and recorded on `metrics` individually, so a
+/// request that succeeds on a later attempt reports that attempt's round
+/// trip, not the cumulative time spent retrying and backing off.
+async fn call_inference_with_retry(
+    client: &Client,
+    config: &InferenceConfig,
+    csv: &str,
+    metrics: &Metrics,
+) -> Result<InferenceOutcome, String> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+
+        let started = Instant::now();
+        let sent = client
+            .post(INFERENCE_ENDPOINT)
+            .timeout(config.timeout)
+            .json(&serde_json::json!({ "csv_data": csv }))
+            .send()
+            .await;
+
+        match sent {
+            Ok(resp) => {
+                let status = resp.status();
+                let retryable = status.is_server_error();
+                if !retryable || attempt >= config.max_attempts {
+                    let text = resp.text().await.unwrap_or_default();
+                    metrics.observe_inference_latency(started.elapsed());
+                    let json = serde_json::from_str::<Value>(&text).ok();
+                    return Ok(InferenceOutcome { status, json, text, attempts: attempt });
+                }
+                metrics.observe_inference_latency(started.elapsed());
+                error!("inference attempt {attempt} got {status}, retrying");
+            }
+            Err(e) => {
+                metrics.observe_inference_latency(started.elapsed());
+                let retryable = e.is_timeout() || e.is_connect();
+                if !retryable || attempt >= config.max_attempts {
+                    return Err(e.to_string());
+                }
+                error!("inference attempt {attempt} failed: {e}, retrying");
+            }
+        }
+
+        tokio::time::sleep(backoff_for_attempt(config, attempt)).await;
+    }
+}
+
+/// Pushed to every subscribed `/ws` client as newly accepted CSV rows land,
+/// and once more with the final inference result when a demo stops.
+#[derive(Clone, Debug)]
+enum DemoEvent {
+    Packet(String),
+    Result(Value),
+}
+
+impl DemoEvent {
+    fn into_ws_text(self) -> String {
+        match self {
+            DemoEvent::Packet(line) => serde_json::json!({ "type": "packet", "line": line }).to_string(),
+            DemoEvent::Result(result) => serde_json::json!({ "type": "result", "result": result }).to_string(),
+        }
+    }
+}
 
 /// Path for your local test CSV
 const TEST_CSV_PATH: &str = "/root/InferenceAPI/test_data/test_1.csv";
@@ -20,32 +198,431 @@ const MAX_SAMPLES: usize = 120;
 // debug flag
 const DEBUG: bool = false;
 
-/// In‑memory CSV accumulator
+/// Upper bounds (seconds) of the inference-latency histogram buckets.
+/// The final `+Inf` bucket is implicit and always present.
+const LATENCY_BUCKETS: [f64; 8] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Prometheus counters/histogram for demo and inference observability.
+///
+/// All counters are atomics (the per-filter ones held in a `DashMap`) so
+/// handlers can bump them without taking
+/// the `cache` lock; `/metrics` renders a snapshot in the text exposition
+/// format on every scrape.
 #[derive(Default)]
+struct Metrics {
+    packets_received_total: AtomicU64,
+    packets_rejected_duplicate_total: AtomicU64,
+    buffer_full_rejections_total: AtomicU64,
+    demos_started_total: AtomicU64,
+    demos_stopped_total: AtomicU64,
+    /// Count of inference calls landing in each `LATENCY_BUCKETS` bucket
+    /// (non-cumulative; cumulative sums are computed at render time).
+    inference_latency_bucket_counts: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    inference_latency_sum_ms: AtomicU64,
+    inference_latency_count: AtomicU64,
+    /// Rows dropped by each [`PacketFilter`] in the chain, keyed by filter name
+    filter_rejections: DashMap<&'static str, AtomicU64>,
+}
+
+impl Metrics {
+    fn record_packet_received(&self) {
+        self.packets_received_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_packet_rejected_duplicate(&self, n: u64) {
+        self.packets_rejected_duplicate_total.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Bumps the per-filter counter surfaced on `/metrics` so operators can
+    /// see which stage of the chain is dropping rows.
+    fn record_filter_rejection(&self, filter: &'static str, n: u64) {
+        self.filter_rejections
+            .entry(filter)
+            .or_insert_with(|| AtomicU64::new(0))
+            .fetch_add(n, Ordering::Relaxed);
+    }
+
+    fn record_buffer_full(&self) {
+        self.buffer_full_rejections_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_demo_started(&self) {
+        self.demos_started_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_demo_stopped(&self) {
+        self.demos_stopped_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn observe_inference_latency(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.inference_latency_bucket_counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.inference_latency_sum_ms
+            .fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+        self.inference_latency_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render a full scrape in the Prometheus text exposition format.
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP demo_packets_received_total Packets accepted into a demo's CSV buffer\n");
+        out.push_str("# TYPE demo_packets_received_total counter\n");
+        out.push_str(&format!(
+            "demo_packets_received_total {}\n",
+            self.packets_received_total.load(Ordering::Relaxed)
+        ));
+
+        out.push_str("# HELP demo_packets_rejected_duplicate_total Packet rows dropped as duplicate/out-of-order\n");
+        out.push_str("# TYPE demo_packets_rejected_duplicate_total counter\n");
+        out.push_str(&format!(
+            "demo_packets_rejected_duplicate_total {}\n",
+            self.packets_rejected_duplicate_total.load(Ordering::Relaxed)
+        ));
+
+        out.push_str("# HELP demo_buffer_full_rejections_total Uploads rejected because MAX_SAMPLES was reached\n");
+        out.push_str("# TYPE demo_buffer_full_rejections_total counter\n");
+        out.push_str(&format!(
+            "demo_buffer_full_rejections_total {}\n",
+            self.buffer_full_rejections_total.load(Ordering::Relaxed)
+        ));
+
+        out.push_str("# HELP demo_starts_total Demo runs started\n");
+        out.push_str("# TYPE demo_starts_total counter\n");
+        out.push_str(&format!("demo_starts_total {}\n", self.demos_started_total.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP demo_stops_total Demo runs stopped\n");
+        out.push_str("# TYPE demo_stops_total counter\n");
+        out.push_str(&format!("demo_stops_total {}\n", self.demos_stopped_total.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP inference_request_duration_seconds Round-trip latency of calls to the inference microservice\n");
+        out.push_str("# TYPE inference_request_duration_seconds histogram\n");
+        let mut cumulative = 0u64;
+        for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            cumulative += self.inference_latency_bucket_counts[i].load(Ordering::Relaxed);
+            out.push_str(&format!(
+                "inference_request_duration_seconds_bucket{{le=\"{bound}\"}} {cumulative}\n"
+            ));
+        }
+        cumulative += self.inference_latency_bucket_counts[LATENCY_BUCKETS.len()].load(Ordering::Relaxed);
+        out.push_str(&format!(
+            "inference_request_duration_seconds_bucket{{le=\"+Inf\"}} {cumulative}\n"
+        ));
+        let sum_seconds = self.inference_latency_sum_ms.load(Ordering::Relaxed) as f64 / 1000.0;
+        out.push_str(&format!("inference_request_duration_seconds_sum {sum_seconds}\n"));
+        out.push_str(&format!(
+            "inference_request_duration_seconds_count {}\n",
+            self.inference_latency_count.load(Ordering::Relaxed)
+        ));
+
+        out.push_str("# HELP demo_filter_rejections_total Rows dropped by a packet-processing filter\n");
+        out.push_str("# TYPE demo_filter_rejections_total counter\n");
+        for entry in self.filter_rejections.iter() {
+            out.push_str(&format!(
+                "demo_filter_rejections_total{{filter=\"{}\"}} {}\n",
+                entry.key(),
+                entry.value().load(Ordering::Relaxed)
+            ));
+        }
+
+        out
+    }
+}
+
+/// One CSV data row, pre-split off its packet but not yet parsed further.
+type Row = String;
+
+/// Why a [`PacketFilter`] rejected something.
+#[derive(Debug, Clone)]
+enum RejectReason {
+    Utf8,
+    Schema { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectReason::Utf8 => write!(f, "CSV must be UTF-8"),
+            RejectReason::Schema { expected, found } => {
+                write!(f, "expected {expected} columns, found {found}")
+            }
+        }
+    }
+}
+
+/// One stage of the `/upload` processing pipeline. Stages run in the order
+/// they're configured; a stage rejects the *whole* packet by returning `Err`,
+/// or drops just the offending rows by removing them from `rows` and
+/// returning `Ok(())`. Implementations are stateful per session (e.g. "what
+/// was the last timestamp"), so a fresh chain is built for every [`Session`].
+trait PacketFilter: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn process(&self, rows: &mut Vec<Row>, metrics: &Metrics) -> Result<(), RejectReason>;
+}
+
+/// Rejects a packet outright the first time a row's column count disagrees
+/// with the count established by this session's first row.
+struct ColumnCountFilter {
+    expected_columns: Mutex<Option<usize>>,
+}
+
+impl ColumnCountFilter {
+    fn new() -> Self {
+        Self { expected_columns: Mutex::new(None) }
+    }
+}
+
+impl PacketFilter for ColumnCountFilter {
+    fn name(&self) -> &'static str {
+        "column_count"
+    }
+
+    fn process(&self, rows: &mut Vec<Row>, metrics: &Metrics) -> Result<(), RejectReason> {
+        let mut expected = self.expected_columns.lock();
+        for row in rows.iter() {
+            let found = row.split(',').count();
+            match *expected {
+                None => *expected = Some(found),
+                Some(exp) if exp != found => {
+                    metrics.record_filter_rejection(self.name(), 1);
+                    return Err(RejectReason::Schema { expected: exp, found });
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Drops rows whose leading timestamp column doesn't strictly increase —
+/// the duplicate/out-of-order check the buffer used to do inline.
+#[derive(Default)]
+struct MonotonicTimestampFilter {
+    last_ts: Mutex<Option<f64>>,
+}
+
+impl PacketFilter for MonotonicTimestampFilter {
+    fn name(&self) -> &'static str {
+        "monotonic_timestamp"
+    }
+
+    fn process(&self, rows: &mut Vec<Row>, metrics: &Metrics) -> Result<(), RejectReason> {
+        let mut last_ts = self.last_ts.lock();
+        let mut rejected = 0u64;
+        rows.retain(|row| {
+            let ts_str = row.split(',').next().unwrap_or("").trim_matches('"');
+            if let Ok(ts) = ts_str.parse::<f64>() {
+                if last_ts.map_or(false, |prev| ts <= prev) {
+                    rejected += 1;
+                    return false;
+                }
+                *last_ts = Some(ts);
+            }
+            true
+        });
+        if rejected > 0 {
+            metrics.record_packet_rejected_duplicate(rejected);
+            metrics.record_filter_rejection(self.name(), rejected);
+        }
+        Ok(())
+    }
+}
+
+/// Rescales configured non-timestamp columns (e.g. volts → microvolts)
+/// before the row ever reaches the buffer. A no-op unless `EEG_UNIT_SCALES`
+/// is set, so it costs nothing for deployments that don't need it.
+struct UnitNormalizationFilter {
+    /// (column index, multiplier) pairs
+    scales: Vec<(usize, f64)>,
+}
+
+impl PacketFilter for UnitNormalizationFilter {
+    fn name(&self) -> &'static str {
+        "unit_normalization"
+    }
+
+    fn process(&self, rows: &mut Vec<Row>, _metrics: &Metrics) -> Result<(), RejectReason> {
+        if self.scales.is_empty() {
+            return Ok(());
+        }
+        for row in rows.iter_mut() {
+            let mut cells: Vec<String> = row.split(',').map(str::to_string).collect();
+            for &(idx, scale) in &self.scales {
+                if let Some(cell) = cells.get_mut(idx) {
+                    if let Ok(value) = cell.trim_matches('"').parse::<f64>() {
+                        *cell = (value * scale).to_string();
+                    }
+                }
+            }
+            *row = cells.join(",");
+        }
+        Ok(())
+    }
+}
+
+/// Drops rows containing an amplitude artifact: any non-timestamp column
+/// whose magnitude exceeds a configurable threshold.
+struct ArtifactRejector {
+    max_abs_value: f64,
+}
+
+impl PacketFilter for ArtifactRejector {
+    fn name(&self) -> &'static str {
+        "artifact_rejector"
+    }
+
+    fn process(&self, rows: &mut Vec<Row>, metrics: &Metrics) -> Result<(), RejectReason> {
+        let max = self.max_abs_value;
+        let mut rejected = 0u64;
+        rows.retain(|row| {
+            let in_range = row
+                .split(',')
+                .skip(1)
+                .all(|cell| cell.trim_matches('"').parse::<f64>().map_or(true, |v| v.abs() <= max));
+            if !in_range {
+                rejected += 1;
+            }
+            in_range
+        });
+        if rejected > 0 {
+            metrics.record_filter_rejection(self.name(), rejected);
+        }
+        Ok(())
+    }
+}
+
+/// Default stage order, used when `PACKET_FILTER_CHAIN` isn't set: schema
+/// must hold before timestamp/value checks run, and normalization should
+/// happen before the artifact threshold is applied.
+const DEFAULT_FILTER_CHAIN: &str = "column_count,monotonic_timestamp,unit_normalization,artifact_rejector";
+
+/// Env-driven knobs for the filter chain, read once at startup; each
+/// [`Session`] gets its own stateful chain built from this shared config.
+#[derive(Clone)]
+struct FilterChainConfig {
+    /// Stage names in the order they should run, as read from
+    /// `PACKET_FILTER_CHAIN` (or [`DEFAULT_FILTER_CHAIN`] if unset) — lets an
+    /// operator disable or reorder stages without a rebuild.
+    stages: Vec<String>,
+    artifact_max_abs: f64,
+    unit_scales: Vec<(usize, f64)>,
+}
+
+impl FilterChainConfig {
+    fn from_env() -> Self {
+        Self {
+            stages: std::env::var("PACKET_FILTER_CHAIN")
+                .unwrap_or_else(|_| DEFAULT_FILTER_CHAIN.to_string())
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            artifact_max_abs: env_var_or("ARTIFACT_MAX_ABS_VALUE", 5000.0),
+            unit_scales: parse_unit_scales_env(),
+        }
+    }
+
+    /// Builds a fresh, per-session chain from `self.stages`. Unknown stage
+    /// names are logged and skipped rather than failing the demo outright.
+    fn build_chain(&self) -> Vec<Box<dyn PacketFilter>> {
+        self.stages
+            .iter()
+            .filter_map(|name| match name.as_str() {
+                "column_count" => Some(Box::new(ColumnCountFilter::new()) as Box<dyn PacketFilter>),
+                "monotonic_timestamp" => Some(Box::new(MonotonicTimestampFilter::default()) as Box<dyn PacketFilter>),
+                "unit_normalization" => {
+                    Some(Box::new(UnitNormalizationFilter { scales: self.unit_scales.clone() }) as Box<dyn PacketFilter>)
+                }
+                "artifact_rejector" => {
+                    Some(Box::new(ArtifactRejector { max_abs_value: self.artifact_max_abs }) as Box<dyn PacketFilter>)
+                }
+                other => {
+                    error!("unknown PACKET_FILTER_CHAIN stage {other:?}, skipping");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Parses `EEG_UNIT_SCALES` as comma-separated `column:multiplier` pairs,
+/// e.g. `"1:1000000,2:1000000"` to convert channels 1 and 2 from volts to
+/// microvolts. Malformed entries are skipped rather than failing startup.
+fn parse_unit_scales_env() -> Vec<(usize, f64)> {
+    std::env::var("EEG_UNIT_SCALES")
+        .ok()
+        .map(|raw| {
+            raw.split(',')
+                .filter_map(|pair| {
+                    let (idx, scale) = pair.split_once(':')?;
+                    Some((idx.trim().parse().ok()?, scale.trim().parse().ok()?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Why `CsvCache::push_packet` rejected an upload.
+enum PushError {
+    BufferFull,
+    Rejected(RejectReason),
+}
+
+impl PushError {
+    fn into_response(self) -> HttpResponse {
+        match self {
+            PushError::BufferFull => HttpResponse::NoContent().finish(),
+            PushError::Rejected(reason) => HttpResponse::BadRequest().body(reason.to_string()),
+        }
+    }
+}
+
+/// In‑memory CSV accumulator
 struct CsvCache {
     buf: String,
     header_seen: bool,
     samples: usize,
-    last_ts: Option<f64>,
+    filters: Vec<Box<dyn PacketFilter>>,
 }
 
 impl CsvCache {
-    fn reset(&mut self) {
-        self.buf.clear();
-        self.header_seen = false;
-        self.samples = 0;
-        self.last_ts = None;
+    fn new(filters: Vec<Box<dyn PacketFilter>>) -> Self {
+        Self { buf: String::new(), header_seen: false, samples: 0, filters }
     }
 
-    fn push_packet(&mut self, bytes: &[u8]) -> Result<(), &'static str> {
+    /// Appends newly accepted rows and returns them (empty if nothing new was
+    /// kept), so callers can forward the increment to streaming inference
+    /// without re-diffing the buffer.
+    fn push_packet(
+        &mut self,
+        bytes: &[u8],
+        tx: &broadcast::Sender<DemoEvent>,
+        metrics: &Metrics,
+    ) -> Result<Vec<Row>, PushError> {
         if self.samples >= MAX_SAMPLES {
-            return Err("demo buffer full");
+            metrics.record_buffer_full();
+            return Err(PushError::BufferFull);
         }
 
-        let text   = std::str::from_utf8(bytes).map_err(|_| "CSV must be UTF-8")?;
+        // UTF-8 decoding can't be expressed as a `PacketFilter` stage: `Row` is
+        // a `String`, which the type system already guarantees is valid UTF-8,
+        // so there's no way to hand an invalid-UTF-8 row to `process()` for a
+        // filter to drop. We still want it visible on `/metrics` like any
+        // other rejection, so record it the same way a filter would.
+        let text = match std::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => {
+                metrics.record_filter_rejection("utf8_decode", 1);
+                return Err(PushError::Rejected(RejectReason::Utf8));
+            }
+        };
         let mut it = text.lines();
 
-        // ─── 1. header handling ───────────────────────────────────────────────
+        // ─── 1. header handling — structural framing, not a content filter ────
         if !self.header_seen {
             if let Some(hdr) = it.next() {
                 self.buf.push_str(hdr);
@@ -57,52 +634,198 @@ impl CsvCache {
             let _ = it.next();
         }
 
-        // ─── 2. append rows, remembering whether we kept at least one ────────
-        let mut accepted_any = false;
-
-        for line in it {
-            let ts_str = line.split(',').next().unwrap_or("").trim_matches('"');
-
-            if let Ok(ts) = ts_str.parse::<f64>() {
-                if self.last_ts.map_or(false, |prev| ts <= prev) {
-                    continue;                      // duplicate or out-of-order
-                }
-                self.last_ts = Some(ts);
-            }
+        // ─── 2. run the configured filter chain over this packet's rows ───────
+        let mut rows: Vec<Row> = it.map(str::to_string).collect();
+        for filter in &self.filters {
+            filter.process(&mut rows, metrics).map_err(PushError::Rejected)?;
+        }
 
-            self.buf.push_str(line);
+        // ─── 3. append what survived, remembering whether we kept anything ────
+        for row in &rows {
+            self.buf.push_str(row);
             self.buf.push('\n');
-            accepted_any = true;
+
+            // best-effort: no live viewers is not an error
+            let _ = tx.send(DemoEvent::Packet(row.clone()));
         }
 
-        // ─── 3. bump the packet counter *only* when we really added data ─────
-        if accepted_any {
+        if !rows.is_empty() {
             self.samples += 1;
+            metrics.record_packet_received();
         }
 
-        Ok(())
+        Ok(rows)
     }
 }
 
+/// One caller's demo run: its own buffer, its own `/ws` subscribers, and a
+/// last-touched timestamp so the idle sweep knows when to reclaim it.
+struct Session {
+    cache: Mutex<CsvCache>,
+    tx: broadcast::Sender<DemoEvent>,
+    last_seen: Mutex<Instant>,
+    /// CSV header row, captured once so the stream worker can assemble a
+    /// self-contained CSV from just the sliding window.
+    header: Mutex<Option<String>>,
+    /// Most recent `STREAM_WINDOW_SIZE` accepted rows, fed to the inference
+    /// microservice as the demo streams in.
+    stream_window: Mutex<VecDeque<String>>,
+    /// Latest result from pipelined streaming inference, surfaced on `/data`.
+    partial_result: Mutex<Option<Value>>,
+}
+
+impl Session {
+    fn new(filter_config: &FilterChainConfig) -> Self {
+        let (tx, _rx) = broadcast::channel(BROADCAST_CAPACITY);
+        Self {
+            cache: Mutex::new(CsvCache::new(filter_config.build_chain())),
+            tx,
+            last_seen: Mutex::new(Instant::now()),
+            header: Mutex::new(None),
+            stream_window: Mutex::new(VecDeque::with_capacity(STREAM_WINDOW_SIZE)),
+            partial_result: Mutex::new(None),
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_seen.lock() = Instant::now();
+    }
+}
+
+/// Parse a `{session_id}` path segment, rejecting malformed UUIDs with a 400
+/// rather than letting a lookup silently miss.
+fn parse_session_id(raw: &str) -> Result<SessionId, HttpResponse> {
+    Uuid::parse_str(raw).map_err(|_| HttpResponse::BadRequest().body("Invalid session id"))
+}
+
 type SharedData = web::Data<AppState>;
 
+/// One browser viewer following a single session's demo over `/ws`.
+///
+/// Subscribes to that `Session::tx` on connect and relays every [`DemoEvent`]
+/// as a JSON text frame; client-sent frames are just echoed/answered so a
+/// viewer can sanity-check the connection is alive.
+struct DemoSocket {
+    hb: Instant,
+    rx: Option<broadcast::Receiver<DemoEvent>>,
+}
+
+impl DemoSocket {
+    fn new(rx: broadcast::Receiver<DemoEvent>) -> Self {
+        Self { hb: Instant::now(), rx: Some(rx) }
+    }
+
+    /// Periodically pings the client and drops the connection if it stops
+    /// answering, so a dead Pi/browser link doesn't pin an actor forever.
+    fn heartbeat(ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT {
+                info!("ws client timed out, dropping connection");
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"");
+        });
+    }
+}
+
+impl Actor for DemoSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        Self::heartbeat(ctx);
+        if let Some(rx) = self.rx.take() {
+            ctx.add_stream(BroadcastStream::new(rx));
+        }
+    }
+}
+
+/// Forwards broadcast demo events to the connected client.
+impl ActixStreamHandler<Result<DemoEvent, BroadcastStreamRecvError>> for DemoSocket {
+    fn handle(&mut self, item: Result<DemoEvent, BroadcastStreamRecvError>, ctx: &mut Self::Context) {
+        match item {
+            Ok(event) => ctx.text(event.into_ws_text()),
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                error!("ws subscriber lagged, skipped {skipped} events");
+            }
+        }
+    }
+}
+
+/// Handles the raw websocket control protocol from the client.
+impl ActixStreamHandler<Result<ws::Message, ws::ProtocolError>> for DemoSocket {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                self.hb = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {
+                self.hb = Instant::now();
+            }
+            Ok(ws::Message::Text(text)) => ctx.text(text),
+            Ok(ws::Message::Binary(bin)) => ctx.binary(bin),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(ws::Message::Continuation(_)) | Ok(ws::Message::Nop) => {}
+            Err(e) => {
+                error!("ws protocol error: {e}");
+                ctx.stop();
+            }
+        }
+    }
+}
+
+/// Browser entry point: subscribes the new socket to one session's live feed.
+#[get("/ws/{session_id}")]
+async fn ws_index(
+    req: HttpRequest,
+    stream: web::Payload,
+    path: web::Path<String>,
+    app: SharedData,
+) -> Result<HttpResponse, Error> {
+    let session_id = match parse_session_id(&path) {
+        Ok(id) => id,
+        Err(resp) => return Ok(resp),
+    };
+    let Some(session) = app.sessions.get(&session_id) else {
+        return Ok(HttpResponse::NotFound().body("No such session"));
+    };
+    session.touch();
+    ws::start(DemoSocket::new(session.tx.subscribe()), &req, stream)
+}
+
 /// Kubernetes/NGINX healthcheck
 #[get("/health")]
 async fn health() -> impl Responder {
     HttpResponse::Ok().body("OK")
 }
 
-/// Polling endpoint: return current CSV buffer if demo is active
-#[get("/data")]
-async fn get_data(app: SharedData) -> impl Responder {
-    // lock‑free check
-    if !app.active.load(Ordering::Acquire) {
+/// Prometheus scrape target for demo/inference observability
+#[get("/metrics")]
+async fn metrics_handler(app: SharedData) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(app.metrics.render())
+}
+
+/// Polling endpoint: return current CSV buffer for one session, if active
+#[get("/data/{session_id}")]
+async fn get_data(path: web::Path<String>, app: SharedData) -> impl Responder {
+    let session_id = match parse_session_id(&path) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let Some(session) = app.sessions.get(&session_id) else {
         return HttpResponse::NoContent().finish();
-    }
+    };
+    session.touch();
 
     // snapshot buffer + sample count
     let (csv, samples) = {
-        let guard = app.cache.lock();
+        let guard = session.cache.lock();
         (guard.buf.clone(), guard.samples)
     };
 
@@ -116,27 +839,50 @@ async fn get_data(app: SharedData) -> impl Responder {
     if samples >= MAX_SAMPLES {
         builder.insert_header(("X-Demo-Complete", "true"));
     }
+    if let Some(partial) = session.partial_result.lock().clone() {
+        builder.insert_header(("X-Partial-Result", partial.to_string()));
+    }
     builder.body(csv)
 }
 
-/// Raspberry Pi streams CSV packets here
-#[post("/upload")]
-async fn upload_csv(body: web::Bytes, app: SharedData) -> impl Responder {
-    if !app.active.load(Ordering::Acquire) {
+/// Raspberry Pi streams CSV packets here for one session
+#[post("/upload/{session_id}")]
+async fn upload_csv(path: web::Path<String>, body: web::Bytes, app: SharedData) -> impl Responder {
+    let session_id = match parse_session_id(&path) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let Some(session) = app.sessions.get(&session_id) else {
         return HttpResponse::BadRequest().body("No active demo");
-    }
+    };
+    session.touch();
+
+    let accepted = {
+        let mut guard = session.cache.lock();
+        match guard.push_packet(&body, &session.tx, &app.metrics) {
+            Ok(rows) => rows,
+            Err(e) => return e.into_response(),
+        }
+    };
+
+    // Hand the increment to the background streaming-inference worker; the
+    // request itself never waits on the inference round trip.
+    if !accepted.is_empty() {
+        let mut header = session.header.lock();
+        if header.is_none() {
+            *header = session.cache.lock().buf.lines().next().map(str::to_string);
+        }
+        drop(header);
 
-    let mut guard = app.cache.lock();
-    match guard.push_packet(&body) {
-        Ok(())                  => HttpResponse::Ok().body("Packet cached"),
-        Err("demo buffer full") => HttpResponse::NoContent().finish(),
-        Err(e)                  => HttpResponse::BadRequest().body(e),
+        let _ = app.stream_tx.send(StreamChunk { session_id, rows: accepted });
     }
+
+    HttpResponse::Ok().body("Packet cached")
 }
 
 /// Quick inference on a static test CSV
 #[get("/inference")]
-async fn inference_proxy() -> impl Responder {
+async fn inference_proxy(app: SharedData) -> impl Responder {
     let csv = match fs::read_to_string(TEST_CSV_PATH) {
         Ok(c) => c,
         Err(e) => {
@@ -147,61 +893,49 @@ async fn inference_proxy() -> impl Responder {
     };
 
     let client = Client::new();
-    let resp = match client
-        .post(INFERENCE_ENDPOINT)
-        .json(&serde_json::json!({ "csv_data": csv }))
-        .send()
-        .await
-    {
-        Ok(r) => r,
+    let outcome = call_inference_with_retry(&client, &app.inference, &csv, &app.metrics).await;
+
+    match outcome {
+        Ok(outcome) => outcome.into_response(),
         Err(e) => {
             error!("Inference request error: {e}");
-            return HttpResponse::InternalServerError()
-                .body(format!("Request error: {e}"));
+            HttpResponse::InternalServerError().body(format!("Request error: {e}"))
         }
-    };
-
-    let status = resp.status();
-    let text = resp.text().await.unwrap_or_default();
-    if !status.is_success() {
-        return HttpResponse::InternalServerError()
-            .body(format!("Inference service error: {status} – {text}"));
-    }
-
-    match serde_json::from_str::<Value>(&text) {
-        Ok(json) => HttpResponse::Ok().json(json),
-        Err(_) => HttpResponse::Ok().body(text),
     }
 }
 
-/// Start a new demo run
+/// Start a new demo run; mints and returns a fresh session id
 #[post("/demo/start")]
 async fn start_demo(app: SharedData) -> impl Responder {
-    if app.active.swap(true, Ordering::AcqRel) {
-        return HttpResponse::BadRequest().body("Demo already running");
-    }
-    app.cache.lock().reset();
-    HttpResponse::Ok().body("Recording started")
+    let session_id = Uuid::new_v4();
+    app.sessions.insert(session_id, Session::new(&app.filter_config));
+    app.metrics.record_demo_started();
+    HttpResponse::Ok().json(serde_json::json!({ "session_id": session_id.to_string() }))
 }
 
-/// Stop demo and run inference on collected data
-#[post("/demo/stop")]
-async fn stop_demo(app: SharedData) -> impl Responder {
-    if !app.active.swap(false, Ordering::AcqRel) {
+/// Stop one session's demo and run inference on its collected data
+#[post("/demo/stop/{session_id}")]
+async fn stop_demo(path: web::Path<String>, app: SharedData) -> impl Responder {
+    let session_id = match parse_session_id(&path) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let Some((_, session)) = app.sessions.remove(&session_id) else {
         return HttpResponse::BadRequest().body("No demo running");
-    }
-
-    let mut csv = {
-        let guard = app.cache.lock();
-        guard.buf.clone()
     };
+    app.metrics.record_demo_stopped();
+
+    let mut csv = session.cache.lock().buf.clone();
 
     if csv.trim().is_empty() {
         return HttpResponse::Ok().body("No data collected");
     }
 
     // save the CSV to a file
-    let filename = format!("/tmp/demo_{}.csv", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
+    let filename = format!(
+        "/tmp/demo_{session_id}_{}.csv",
+        chrono::Utc::now().format("%Y%m%d_%H%M%S")
+    );
     if let Err(e) = fs::write(&filename, &csv) {
         error!("Failed to write CSV file: {e}");
         return HttpResponse::InternalServerError()
@@ -214,39 +948,142 @@ async fn stop_demo(app: SharedData) -> impl Responder {
         csv = fs::read_to_string(TEST_CSV_PATH).unwrap_or_default();
     }
 
-    // send the CSV to the inference service
+    // send the CSV to the inference service — the file above is already on
+    // disk, so a run that takes minutes to collect is never lost to a flaky
+    // inference call here
     let client = Client::new();
-    let resp = match client
-        .post(INFERENCE_ENDPOINT)
-        .json(&serde_json::json!({ "csv_data": csv }))
-        .send()
-        .await
-    {
-        Ok(r) => r,
+    let outcome = call_inference_with_retry(&client, &app.inference, &csv, &app.metrics).await;
+
+    match outcome {
+        Ok(outcome) => {
+            if outcome.status.is_success() {
+                if let Some(json) = outcome.json.clone() {
+                    let _ = session.tx.send(DemoEvent::Result(json));
+                }
+            }
+            outcome.into_response()
+        }
         Err(e) => {
             error!("Inference request error: {e}");
-            return HttpResponse::InternalServerError()
-                .body(format!("Request error: {e}"));
+            HttpResponse::InternalServerError().body(format!("Request error: {e}"))
         }
-    };
+    }
+}
 
-    let status = resp.status();
-    let body = resp.text().await.unwrap_or_default();
-    if !status.is_success() {
-        return HttpResponse::InternalServerError()
-            .body(format!("Inference error: {status} – {body}"));
+/// Background worker: drains [`StreamChunk`]s as `/upload` accepts rows,
+/// keeps each session's sliding window up to date, and posts it to the
+/// inference microservice so `/data` can surface a running prediction well
+/// before `/demo/stop` runs the final pass over the whole buffer.
+///
+/// A fast uploader can enqueue several chunks for the same session before we
+/// get back around to them, so each pass drains everything already queued
+/// and coalesces it per session into a single inference call, instead of
+/// firing one HTTP request per accepted packet.
+async fn run_stream_worker(app: web::Data<AppState>, mut rx: mpsc::UnboundedReceiver<StreamChunk>) {
+    let client = Client::new();
+
+    while let Some(first) = rx.recv().await {
+        let mut order = vec![first.session_id];
+        let mut batched: HashMap<SessionId, Vec<String>> = HashMap::new();
+        batched.insert(first.session_id, first.rows);
+
+        while let Ok(chunk) = rx.try_recv() {
+            batched
+                .entry(chunk.session_id)
+                .and_modify(|rows| rows.extend(chunk.rows.iter().cloned()))
+                .or_insert_with(|| {
+                    order.push(chunk.session_id);
+                    chunk.rows
+                });
+        }
+
+        for session_id in order {
+            let Some(rows) = batched.remove(&session_id) else { continue };
+            run_stream_inference(&app, &client, session_id, rows).await;
+        }
     }
+}
+
+/// Feeds one session's coalesced batch of newly accepted rows into its
+/// sliding window and posts the resulting CSV to the inference microservice.
+async fn run_stream_inference(app: &web::Data<AppState>, client: &Client, session_id: SessionId, rows: Vec<String>) {
+    let window_csv = {
+        let Some(session) = app.sessions.get(&session_id) else {
+            return;
+        };
+
+        let mut window = session.stream_window.lock();
+        for row in rows {
+            if window.len() >= STREAM_WINDOW_SIZE {
+                window.pop_front();
+            }
+            window.push_back(row);
+        }
+
+        let mut csv = session.header.lock().clone().unwrap_or_default();
+        csv.push('\n');
+        for row in window.iter() {
+            csv.push_str(row);
+            csv.push('\n');
+        }
+        csv
+    }; // `session` (a DashMap ref) is dropped here, before the await below
+
+    let started = Instant::now();
+    let resp = client
+        .post(INFERENCE_ENDPOINT)
+        .json(&serde_json::json!({ "csv_data": window_csv }))
+        .send()
+        .await;
+    app.metrics.observe_inference_latency(started.elapsed());
+
+    let result = match resp {
+        Ok(r) if r.status().is_success() => match r.json::<Value>().await {
+            Ok(json) => json,
+            Err(e) => {
+                error!("streaming inference returned non-JSON body: {e}");
+                return;
+            }
+        },
+        Ok(r) => {
+            error!("streaming inference error: {}", r.status());
+            return;
+        }
+        Err(e) => {
+            error!("streaming inference request error: {e}");
+            return;
+        }
+    };
 
-    match serde_json::from_str::<Value>(&body) {
-        Ok(json) => HttpResponse::Ok().json(json),
-        Err(_) => HttpResponse::Ok().body(body),
+    if let Some(session) = app.sessions.get(&session_id) {
+        *session.partial_result.lock() = Some(result);
     }
 }
 
 // 1) Define your state
 struct AppState {
-    active: AtomicBool,
-    cache:  Mutex<CsvCache>,
+    /// One entry per concurrently running demo, keyed by the id `/demo/start` mints
+    sessions: DashMap<SessionId, Session>,
+    metrics: Metrics,
+    /// Feeds newly accepted rows to [`run_stream_worker`]
+    stream_tx: mpsc::UnboundedSender<StreamChunk>,
+    /// Retry/timeout policy for calls to the inference microservice
+    inference: InferenceConfig,
+    /// Packet-processing chain every new [`Session`] is built with
+    filter_config: FilterChainConfig,
+}
+
+impl AppState {
+    /// Drops sessions nobody has touched within `SESSION_IDLE_TIMEOUT`.
+    fn sweep_idle_sessions(&self) {
+        let before = self.sessions.len();
+        self.sessions
+            .retain(|_, session| session.last_seen.lock().elapsed() < SESSION_IDLE_TIMEOUT);
+        let reclaimed = before - self.sessions.len();
+        if reclaimed > 0 {
+            info!("reclaimed {reclaimed} idle demo session(s)");
+        }
+    }
 }
 
 // 2) In main(), build it like this
@@ -255,23 +1092,68 @@ async fn main() -> std::io::Result<()> {
     // … env_logger setup …
 
     // Directly wrap AppState in Data<T>
+    let (stream_tx, stream_rx) = mpsc::unbounded_channel();
     let state = web::Data::new(AppState {
-        active: AtomicBool::new(false),
-        cache:  Mutex::default(),
+        sessions: DashMap::new(),
+        metrics: Metrics::default(),
+        stream_tx,
+        inference: InferenceConfig::from_env(),
+        filter_config: FilterChainConfig::from_env(),
     });
 
+    // Reclaim buffers from demos nobody ever stopped
+    let sweeper_state = state.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = tokio::time::interval(SESSION_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            sweeper_state.sweep_idle_sessions();
+        }
+    });
+
+    // Pipelined streaming inference, decoupled from the /upload request path
+    actix_web::rt::spawn(run_stream_worker(state.clone(), stream_rx));
+
+    // Connection tuning for flaky Raspberry Pi links — all overridable via
+    // env vars so a deployment can retune without a rebuild.
+    let keep_alive = Duration::from_secs(env_var_or("HTTP_KEEP_ALIVE_SECS", 75));
+    let client_request_timeout = Duration::from_millis(env_var_or("HTTP_CLIENT_REQUEST_TIMEOUT_MS", 5_000));
+    let client_disconnect_timeout = Duration::from_millis(env_var_or("HTTP_CLIENT_DISCONNECT_TIMEOUT_MS", 5_000));
+    let tcp_keepalive = Duration::from_secs(env_var_or("TCP_KEEPALIVE_SECS", 60));
+
+    let addr: SocketAddr = "0.0.0.0:6000".parse().expect("hardcoded bind address is valid");
+    let listener = bind_with_tcp_keepalive(addr, tcp_keepalive)?;
+
     HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
             .app_data(state.clone())
             .service(health)
+            .service(metrics_handler)
             .service(start_demo)      // <‑‑ ADD THESE
             .service(stop_demo)
             .service(upload_csv)
             .service(get_data)
             .service(inference_proxy)
+            .service(ws_index)
     })
-    .bind(("0.0.0.0", 6000))?
+    .keep_alive(keep_alive)
+    .client_request_timeout(client_request_timeout) // a stalled upload gets a 408, not a pinned worker
+    .client_disconnect_timeout(client_disconnect_timeout)
+    .listen(listener)?
     .run()
     .await
 }
+
+/// Binds the listener by hand so we can turn on TCP keep-alive before handing
+/// it to actix — `HttpServer::bind` alone doesn't expose that knob, and a
+/// dead Pi connection would otherwise sit half-open until the OS gives up.
+fn bind_with_tcp_keepalive(addr: SocketAddr, keepalive: Duration) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(keepalive))?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into())
+}